@@ -0,0 +1,27 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use trafficrs::intervals::{union_many, Interval, IntervalCollection};
+
+/// Regularly spaced contact intervals, as built from ADS-B coverage
+fn contacts(size: i64, offset: i64) -> IntervalCollection<i64> {
+    IntervalCollection {
+        elts: (0..size)
+            .map(|i| Interval {
+                start: 10 * i + offset,
+                stop: 10 * i + offset + 7,
+            })
+            .collect(),
+    }
+}
+
+fn union(c: &mut Criterion) {
+    let left = contacts(2_000, 0);
+    let right = contacts(2_000, 5);
+
+    c.bench_function("add", |b| b.iter(|| black_box(&left) + black_box(&right)));
+    c.bench_function("union_many", |b| {
+        b.iter(|| union_many(black_box(&[&left, &right])))
+    });
+}
+
+criterion_group!(benches, union);
+criterion_main!(benches);
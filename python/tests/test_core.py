@@ -0,0 +1,179 @@
+import numpy as np
+import pytest
+from trafficrs.core import (
+    Interval,
+    IntervalCollection,
+    collection_add,
+    collection_addi,
+    collection_add_dt,
+    collection_and_dt,
+    collection_sub_dt,
+    collection_total_duration,
+    interval_xor,
+    process_numpy_datetime_array,
+)
+
+timestamps = np.array(
+    ["2022-03-21T11:10", "2022-03-21T11:12", "2022-03-21T11:15"],
+    dtype="datetime64[ns]",
+)
+
+
+def datetime64(*args: str) -> np.ndarray:
+    return np.array(args, dtype="datetime64[ns]")
+
+
+def python_union(
+    start: np.ndarray, stop: np.ndarray
+) -> list[tuple[int, int]]:
+    res: list[list[int]] = []
+    for a, b in sorted(zip(start.tolist(), stop.tolist())):
+        if res and a <= res[-1][1]:
+            res[-1][1] = max(res[-1][1], b)
+        else:
+            res.append([a, b])
+    return [(a, b) for a, b in res]
+
+
+class TestDatetimeArray:
+    def test_monotonic(self) -> None:
+        res = process_numpy_datetime_array(timestamps.astype(np.int64))
+        assert res["count"] == 3
+        assert res["min"] == timestamps[0].astype(np.int64)
+        assert res["max"] == timestamps[-1].astype(np.int64)
+        assert res["monotonic"]
+
+    def test_not_monotonic(self) -> None:
+        res = process_numpy_datetime_array(timestamps[::-1].astype(np.int64))
+        assert res["min"] == timestamps[0].astype(np.int64)
+        assert not res["monotonic"]
+
+    def test_empty(self) -> None:
+        res = process_numpy_datetime_array(np.array([], dtype=np.int64))
+        assert res["count"] == 0
+        assert res["min"] is None
+        assert res["max"] is None
+
+
+class TestIntervalClass:
+    def test_and(self) -> None:
+        res = Interval(0, 3) & Interval(2, 5)
+        assert res is not None
+        assert (res.start, res.stop) == (2, 3)
+        assert Interval(0, 1) & Interval(2, 5) is None
+
+    def test_add_sub(self) -> None:
+        res = Interval(0, 3) + Interval(2, 5)
+        assert [(i.start, i.stop) for i in res.elts] == [(0, 5)]
+        res = Interval(0, 5) - Interval(2, 3)
+        assert len(res) == 2
+        assert repr(res) == "[[0, 2], [3, 5]]"
+
+    def test_contains(self) -> None:
+        i = Interval(0, 3)
+        assert i.duration() == 3
+        assert 2 in i
+        assert 4 not in i
+        # half-open: start is included, stop is not
+        assert 0 in i
+        assert 3 not in i
+        assert 0 not in Interval(0, 0)
+
+    def test_collection(self) -> None:
+        c = IntervalCollection([Interval(0, 1), Interval(2, 3)])
+        assert len(c) == 2
+        assert repr(c.elts[1]) == "[2, 3]"
+
+    def test_invalid(self) -> None:
+        with pytest.raises(RuntimeError):
+            Interval(3, 0)
+
+
+class TestDatetimeCollection:
+    start1 = datetime64("2022-03-21T11:10", "2022-03-21T11:14")
+    stop1 = datetime64("2022-03-21T11:12", "2022-03-21T11:15")
+    start2 = datetime64("2022-03-21T11:11")
+    stop2 = datetime64("2022-03-21T11:13")
+
+    def test_and(self) -> None:
+        res = collection_and_dt(
+            self.start1, self.stop1, self.start2, self.stop2
+        )
+        assert res["start"].dtype == np.dtype("datetime64[ns]")
+        assert (res["start"] == datetime64("2022-03-21T11:11")).all()
+        assert (res["stop"] == datetime64("2022-03-21T11:12")).all()
+
+    def test_add(self) -> None:
+        res = collection_add_dt(
+            self.start1, self.stop1, self.start2, self.stop2
+        )
+        stop = datetime64("2022-03-21T11:13", "2022-03-21T11:15")
+        assert (res["start"] == self.start1).all()
+        assert (res["stop"] == stop).all()
+
+    def test_sub(self) -> None:
+        res = collection_sub_dt(
+            self.start1, self.stop1, self.start2, self.stop2
+        )
+        stop = datetime64("2022-03-21T11:11", "2022-03-21T11:15")
+        assert (res["start"] == self.start1).all()
+        assert (res["stop"] == stop).all()
+
+
+class TestTotalDuration:
+    def test_overlapping(self) -> None:
+        start = np.array([0, 1, 10], dtype=np.int64)
+        stop = np.array([2, 3, 11], dtype=np.int64)
+        assert collection_total_duration(start, stop) == 4
+
+    def test_empty(self) -> None:
+        empty = np.array([], dtype=np.int64)
+        assert collection_total_duration(empty, empty) == 0
+
+
+class TestIntervalXor:
+    def test_overlap(self) -> None:
+        res = interval_xor(0, 3, 2, 5)
+        assert list(res["start"]) == [0, 3]
+        assert list(res["stop"]) == [2, 5]
+
+    def test_inside(self) -> None:
+        res = interval_xor(0, 5, 2, 3)
+        assert list(res["start"]) == [0, 3]
+        assert list(res["stop"]) == [2, 5]
+
+    def test_equal(self) -> None:
+        res = interval_xor(0, 3, 0, 3)
+        assert len(res["start"]) == 0
+
+
+class TestUnsortedCollection:
+    rng = np.random.default_rng(42)
+    start = rng.integers(0, 10_000_000, 50_000)
+    stop = start + rng.integers(0, 1_000, 50_000)
+
+    def test_add(self) -> None:
+        half = len(self.start) // 2
+        res = collection_add(
+            self.start[:half],
+            self.stop[:half],
+            self.start[half:],
+            self.stop[half:],
+        )
+        expected = python_union(self.start, self.stop)
+        assert list(zip(res["start"], res["stop"])) == expected
+
+    def test_addi(self) -> None:
+        res = collection_addi(self.start, self.stop, 5_000, 5_000_000)
+        start = np.append(self.start, 5_000)
+        stop = np.append(self.stop, 5_000_000)
+        expected = python_union(start, stop)
+        assert list(zip(res["start"], res["stop"])) == expected
+
+    def test_small(self) -> None:
+        start = np.array([4, 0, 1, 0], dtype=np.int64)
+        stop = np.array([5, 2, 3, 2], dtype=np.int64)
+        empty = np.array([], dtype=np.int64)
+        res = collection_add(start, stop, empty, empty)
+        assert list(res["start"]) == [0, 4]
+        assert list(res["stop"]) == [3, 5]
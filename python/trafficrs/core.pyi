@@ -7,10 +7,20 @@ class IntervalCollectionDict(TypedDict):
     start: npt.NDArray[np.int64]
     stop: npt.NDArray[np.int64]
 
+class DatetimeCollectionDict(TypedDict):
+    start: npt.NDArray[np.datetime64]
+    stop: npt.NDArray[np.datetime64]
+
 class IntervalDict(TypedDict):
     start: int
     stop: int
 
+class DatetimeArrayDict(TypedDict):
+    count: int
+    min: int | None
+    max: int | None
+    monotonic: bool
+
 def interval_and(
     start1: int, stop1: int, start2: int, stop2: int
 ) -> IntervalDict: ...
@@ -56,3 +66,49 @@ def collection_subi(
     start2: int,
     stop2: int,
 ) -> IntervalCollectionDict: ...
+def interval_xor(
+    start1: int, stop1: int, start2: int, stop2: int
+) -> IntervalCollectionDict: ...
+def collection_total_duration(
+    start: npt.NDArray[np.int64],
+    stop: npt.NDArray[np.int64],
+) -> int: ...
+def collection_and_dt(
+    start1: npt.NDArray[np.datetime64],
+    stop1: npt.NDArray[np.datetime64],
+    start2: npt.NDArray[np.datetime64],
+    stop2: npt.NDArray[np.datetime64],
+) -> DatetimeCollectionDict: ...
+def collection_add_dt(
+    start1: npt.NDArray[np.datetime64],
+    stop1: npt.NDArray[np.datetime64],
+    start2: npt.NDArray[np.datetime64],
+    stop2: npt.NDArray[np.datetime64],
+) -> DatetimeCollectionDict: ...
+def collection_sub_dt(
+    start1: npt.NDArray[np.datetime64],
+    stop1: npt.NDArray[np.datetime64],
+    start2: npt.NDArray[np.datetime64],
+    stop2: npt.NDArray[np.datetime64],
+) -> DatetimeCollectionDict: ...
+def process_numpy_datetime_array(
+    timestamps: npt.NDArray[np.int64],
+) -> DatetimeArrayDict: ...
+
+class Interval:
+    def __init__(self, start: int, stop: int) -> None: ...
+    @property
+    def start(self) -> int: ...
+    @property
+    def stop(self) -> int: ...
+    def duration(self) -> int: ...
+    def __contains__(self, value: int) -> bool: ...
+    def __and__(self, other: Interval) -> None | Interval: ...
+    def __add__(self, other: Interval) -> IntervalCollection: ...
+    def __sub__(self, other: Interval) -> IntervalCollection: ...
+
+class IntervalCollection:
+    def __init__(self, elts: list[Interval]) -> None: ...
+    @property
+    def elts(self) -> list[Interval]: ...
+    def __len__(self) -> int: ...
@@ -0,0 +1,260 @@
+/// Mean Earth radius, in nautical miles
+pub const EARTH_RADIUS_NM: f64 = 3440.065;
+
+/// Great-circle distance in nautical miles (haversine formula)
+pub fn great_circle_nm(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let delta_phi = (lat2 - lat1).to_radians();
+    let delta_lambda = (lon2 - lon1).to_radians();
+
+    let a = (delta_phi / 2.).sin().powi(2)
+        + phi1.cos() * phi2.cos() * (delta_lambda / 2.).sin().powi(2);
+    2. * EARTH_RADIUS_NM * a.sqrt().min(1.).asin()
+}
+
+/// Initial bearing (forward azimuth) in degrees, within [0, 360)
+pub fn initial_bearing(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let delta_lambda = (lon2 - lon1).to_radians();
+
+    let y = delta_lambda.sin() * phi2.cos();
+    let x = phi1.cos() * phi2.sin() - phi1.sin() * phi2.cos() * delta_lambda.cos();
+    y.atan2(x).to_degrees().rem_euclid(360.)
+}
+
+/// Point reached from (lat, lon) after travelling distance_nm along the
+/// great circle with initial bearing bearing_deg (direct problem)
+///
+/// The resulting longitude is wrapped within [-180, 180).
+pub fn destination(lat: f64, lon: f64, bearing_deg: f64, distance_nm: f64) -> (f64, f64) {
+    let (phi1, lambda1) = (lat.to_radians(), lon.to_radians());
+    let theta = bearing_deg.to_radians();
+    let delta = distance_nm / EARTH_RADIUS_NM;
+
+    let sin_phi2 = phi1.sin() * delta.cos() + phi1.cos() * delta.sin() * theta.cos();
+    let phi2 = sin_phi2.clamp(-1., 1.).asin();
+    let lambda2 = lambda1
+        + (theta.sin() * delta.sin() * phi1.cos()).atan2(delta.cos() - phi1.sin() * sin_phi2);
+
+    (
+        phi2.to_degrees(),
+        (lambda2.to_degrees() + 180.).rem_euclid(360.) - 180.,
+    )
+}
+
+/// Samples the great circle from (lat1, lon1) to (lat2, lon2) every step_nm,
+/// both ends included
+///
+/// Points follow the shortest path, across the antimeridian if need be, with
+/// longitudes wrapped within [-180, 180). Only the ends are returned if
+/// step_nm is not positive.
+pub fn densify(lat1: f64, lon1: f64, lat2: f64, lon2: f64, step_nm: f64) -> Vec<(f64, f64)> {
+    let distance = great_circle_nm(lat1, lon1, lat2, lon2);
+    let bearing = initial_bearing(lat1, lon1, lat2, lon2);
+    let mut res = vec![(lat1, lon1)];
+    if step_nm > 0. {
+        let mut travelled = step_nm;
+        while travelled < distance {
+            res.push(destination(lat1, lon1, bearing, travelled));
+            travelled += step_nm;
+        }
+    }
+    res.push((lat2, lon2));
+    res
+}
+
+/// Decimal degrees from ICAO digits: degrees, then optional minutes and
+/// seconds on two digits each, None if above max_degrees or if minutes or
+/// seconds are not below 60
+fn parse_sexagesimal(digits: &str, degree_len: usize, max_degrees: f64) -> Option<f64> {
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let value = |range: std::ops::Range<usize>| digits[range].parse::<f64>().ok();
+    let degrees = value(0..degree_len)?;
+    let minutes = match digits.len() > degree_len {
+        true => value(degree_len..degree_len + 2)?,
+        false => 0.,
+    };
+    let seconds = match digits.len() > degree_len + 2 {
+        true => value(degree_len + 2..degree_len + 4)?,
+        false => 0.,
+    };
+    let value = degrees + minutes / 60. + seconds / 3600.;
+    match minutes < 60. && seconds < 60. && value <= max_degrees {
+        true => Some(value),
+        false => None,
+    }
+}
+
+/// Parse an ICAO coordinate into decimal degrees (latitude, longitude)
+///
+/// Latitude and longitude must share the same precision:
+/// - degrees, e.g. 54N010E;
+/// - degrees and minutes, e.g. 5430N01020E;
+/// - degrees, minutes and seconds, e.g. 543012N0102030E.
+///
+/// Out of range values (latitude above 90°, longitude above 180°, minutes or
+/// seconds of 60 and more) are rejected.
+pub fn parse_icao_coordinate(s: &str) -> Option<(f64, f64)> {
+    let idx = s.find(['N', 'S'])?;
+    let (lat, rest) = s.split_at(idx);
+    let (lat_hemisphere, rest) = rest.split_at(1);
+    let lon_hemisphere = rest.chars().last()?;
+    let lon = &rest[..rest.len() - lon_hemisphere.len_utf8()];
+
+    if !matches!((lat.len(), lon.len()), (2, 3) | (4, 5) | (6, 7)) {
+        return None;
+    }
+    let latitude = parse_sexagesimal(lat, 2, 90.)?;
+    let longitude = parse_sexagesimal(lon, 3, 180.)?;
+
+    let latitude = match lat_hemisphere {
+        "S" => -latitude,
+        _ => latitude,
+    };
+    let longitude = match lon_hemisphere {
+        'E' => longitude,
+        'W' => -longitude,
+        _ => return None,
+    };
+    Some((latitude, longitude))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{densify, destination, great_circle_nm, initial_bearing, parse_icao_coordinate};
+
+    static LFPG: (f64, f64) = (49.0097, 2.5478);
+    static KJFK: (f64, f64) = (40.6398, -73.7789);
+
+    #[test]
+    fn distance() {
+        assert_eq!(great_circle_nm(LFPG.0, LFPG.1, LFPG.0, LFPG.1), 0.);
+        let d = great_circle_nm(LFPG.0, LFPG.1, KJFK.0, KJFK.1);
+        assert!((d - 3150.).abs() < 10., "{}", d);
+        let back = great_circle_nm(KJFK.0, KJFK.1, LFPG.0, LFPG.1);
+        assert!((d - back).abs() < 1e-9);
+        // one degree of longitude along the equator is 60 NM
+        assert!((great_circle_nm(0., 0., 0., 1.) - 60.).abs() < 0.1);
+    }
+
+    #[test]
+    fn bearing() {
+        assert!((initial_bearing(0., 0., 0., 1.) - 90.).abs() < 1e-9);
+        assert!((initial_bearing(0., 0., 1., 0.)).abs() < 1e-9);
+        assert!((initial_bearing(0., 1., 0., 0.) - 270.).abs() < 1e-9);
+        let b = initial_bearing(LFPG.0, LFPG.1, KJFK.0, KJFK.1);
+        assert!((b - 291.6).abs() < 0.1, "{}", b);
+    }
+
+    #[test]
+    fn direct() {
+        let (lat, lon) = destination(0., 0., 90., 60.);
+        assert!(lat.abs() < 1e-9);
+        assert!((lon - 1.).abs() < 1e-2, "{}", lon);
+
+        let (lat, lon) = destination(LFPG.0, LFPG.1, 291.6, 3150.);
+        assert!(great_circle_nm(lat, lon, KJFK.0, KJFK.1) < 10.);
+
+        // across the antimeridian
+        let (lat, lon) = destination(0., 179.5, 90., 60.);
+        assert!(lat.abs() < 1e-9);
+        assert!((lon + 179.5).abs() < 1e-2, "{}", lon);
+
+        // across the North pole
+        let (lat, lon) = destination(89., 0., 0., 120.);
+        assert!((lat - 89.).abs() < 1e-2, "{}", lat);
+        assert!((lon.abs() - 180.).abs() < 1e-6, "{}", lon);
+    }
+
+    #[test]
+    fn antimeridian() {
+        let (start, end) = (
+            parse_icao_coordinate("64N170E").unwrap(),
+            parse_icao_coordinate("64N170W").unwrap(),
+        );
+        let d = great_circle_nm(start.0, start.1, end.0, end.1);
+        assert!((d - 524.).abs() < 1., "{}", d);
+        let b = initial_bearing(start.0, start.1, end.0, end.1);
+        assert!(b > 80. && b < 90., "{}", b);
+
+        let points = densify(start.0, start.1, end.0, end.1, 50.);
+        assert_eq!(points.len(), 12);
+        assert_eq!(points.last(), Some(&end));
+        for pair in points.windows(2) {
+            let step = great_circle_nm(pair[0].0, pair[0].1, pair[1].0, pair[1].1);
+            assert!(step <= 50. + 1e-6, "{}", step);
+        }
+        // no sample crosses over Europe
+        assert!(points.iter().all(|(_, lon)| lon.abs() >= 170.));
+        // the path bulges northward from the rhumb line
+        assert!(points[3].0 > 64.);
+    }
+
+    #[test]
+    fn densify_bounds() {
+        assert_eq!(densify(0., 0., 0., 1., 0.), vec![(0., 0.), (0., 1.)]);
+        assert_eq!(densify(0., 0., 0., 0., 10.), vec![(0., 0.), (0., 0.)]);
+        let points = densify(0., 0., 0., 1., 25.);
+        assert_eq!(points.len(), 4);
+        assert!((points[1].1 - 25. / 60.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn icao_coordinate() {
+        assert_eq!(parse_icao_coordinate("54N010E"), Some((54., 10.)));
+        assert_eq!(parse_icao_coordinate("46S078W"), Some((-46., -78.)));
+        assert_eq!(
+            parse_icao_coordinate("5430N01020E"),
+            Some((54.5, 10. + 1. / 3.))
+        );
+        let (lat, lon) = parse_icao_coordinate("543012N0102030E").unwrap();
+        assert!((lat - (54. + 30. / 60. + 12. / 3600.)).abs() < 1e-9);
+        assert!((lon - (10. + 20. / 60. + 30. / 3600.)).abs() < 1e-9);
+
+        assert_eq!(parse_icao_coordinate("5430N010E"), None);
+        assert_eq!(parse_icao_coordinate("54N010"), None);
+        assert_eq!(parse_icao_coordinate("54N010X"), None);
+        assert_eq!(parse_icao_coordinate("54N010é"), None);
+        assert_eq!(parse_icao_coordinate("5AN010E"), None);
+        assert_eq!(parse_icao_coordinate("NEBUL"), None);
+        assert_eq!(parse_icao_coordinate(""), None);
+    }
+
+    #[test]
+    fn icao_coordinate_bounds() {
+        assert_eq!(parse_icao_coordinate("5430N01000E"), Some((54.5, 10.)));
+        assert_eq!(parse_icao_coordinate("90N180W"), Some((90., -180.)));
+        assert_eq!(parse_icao_coordinate("9999N01000E"), None);
+        assert_eq!(parse_icao_coordinate("5499N01000E"), None);
+        assert_eq!(parse_icao_coordinate("5460N01000E"), None);
+        assert_eq!(parse_icao_coordinate("9001N01000E"), None);
+        assert_eq!(parse_icao_coordinate("54N181E"), None);
+        assert_eq!(parse_icao_coordinate("5430N01099E"), None);
+        assert_eq!(parse_icao_coordinate("543060N0102030E"), None);
+    }
+
+    #[test]
+    fn icao_coordinate_not_waypoints() {
+        let (lat, lon) = parse_icao_coordinate("543012S0102030W").unwrap();
+        assert!((lat + 54.503333).abs() < 1e-6);
+        assert!((lon + 10.341667).abs() < 1e-6);
+
+        // designators with trailing digits or hemisphere letters
+        for token in [
+            "ABNED2N",
+            "LUMAS12N",
+            "SIRIC4S",
+            "NEBUL1A",
+            "UN857",
+            "N0450F350",
+        ] {
+            assert_eq!(parse_icao_coordinate(token), None, "{}", token);
+        }
+        // mixed precision
+        assert_eq!(parse_icao_coordinate("543012N01020E"), None);
+        assert_eq!(parse_icao_coordinate("5430N0102030E"), None);
+    }
+}
@@ -1,10 +1,11 @@
-use std::cmp::min;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 use std::fmt::Display;
 use std::iter::Sum;
-use std::ops::{Add, BitAnd, Sub};
+use std::ops::{Add, BitAnd, BitXor, Mul, Neg, Sub};
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
 pub struct Interval<T> {
     pub start: T,
     pub stop: T,
@@ -19,16 +20,36 @@ where
     }
 }
 
+impl<T> From<(T, T)> for Interval<T> {
+    fn from((start, stop): (T, T)) -> Interval<T> {
+        Interval { start, stop }
+    }
+}
+
 #[derive(Debug)]
 pub struct IntervalCollection<T> {
     pub elts: Vec<Interval<T>>,
 }
 
+/// Whether adjacent intervals, with the stop of one equal to the start of
+/// the next, are merged when building a union
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MergePolicy {
+    /// Merge overlapping and touching intervals, e.g. [0, 1] and [1, 2]
+    Touching,
+    /// Only merge strictly overlapping intervals, keeping touching ones apart
+    Overlapping,
+}
+
+/// Displays as [[a, b], [c, d], ...], or ∅ for an empty collection
 impl<T> Display for &IntervalCollection<T>
 where
     T: Display,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.elts.is_empty() {
+            return write!(f, "∅");
+        }
         write!(f, "[")?;
         for (i, elt) in self.elts.iter().enumerate() {
             if i > 0 {
@@ -117,58 +138,15 @@ where
     }
 }
 
+/* Implement union between two IntervalCollections, in any order: the result
+is sorted, with overlapping and touching intervals merged */
 impl<T> Add for &IntervalCollection<T>
 where
     T: Ord + Copy,
 {
     type Output = IntervalCollection<T>;
     fn add(self, other: &IntervalCollection<T>) -> IntervalCollection<T> {
-        let mut elts = Vec::new();
-        let mut start = min(&self.elts[0], &other.elts[0]);
-
-        loop {
-            let swiping_line = *(&start.start);
-            let mut horizon = *(&start.stop);
-
-            horizon = self
-                .elts
-                .iter()
-                .chain(other.elts.iter())
-                .filter(|elt| &swiping_line <= &elt.start && &elt.start <= &horizon)
-                .map(|elt| elt.stop)
-                .max()
-                .expect("Unexpected error");
-
-            loop {
-                match self
-                    .elts
-                    .iter()
-                    .chain(other.elts.iter())
-                    .filter(|elt| &elt.start <= &horizon && &horizon < &elt.stop)
-                    .map(|elt| elt.stop)
-                    .max()
-                {
-                    None => break,
-                    Some(x) => horizon = x,
-                }
-            }
-            elts.push(Interval {
-                start: swiping_line,
-                stop: horizon,
-            });
-            match self
-                .elts
-                .iter()
-                .chain(other.elts.iter())
-                .filter(|elt| &elt.start > &horizon)
-                .min()
-            {
-                None => break,
-                Some(x) => start = x,
-            }
-        }
-
-        IntervalCollection { elts: elts }
+        union_many(&[self, other])
     }
 }
 
@@ -254,6 +232,32 @@ where
     }
 }
 
+/* Implement symmetric difference between two Intervals */
+impl<T, Delta> BitXor for Interval<T>
+where
+    T: Sub<T, Output = Delta> + Add<Delta, Output = T> + Copy + PartialOrd,
+    Delta: Copy,
+{
+    type Output = IntervalCollection<T>;
+    fn bitxor(self, other: Interval<T>) -> IntervalCollection<T> {
+        let mut elts = (self - other).elts;
+        elts.extend((other - self).elts);
+        elts.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        IntervalCollection { elts }
+    }
+}
+
+impl<T, Delta> Sub<IntervalCollection<T>> for Interval<T>
+where
+    T: Sub<T, Output = Delta> + Add<Delta, Output = T> + Copy + PartialOrd,
+    Delta: Copy,
+{
+    type Output = IntervalCollection<T>;
+    fn sub(self, other: IntervalCollection<T>) -> IntervalCollection<T> {
+        IntervalCollection { elts: vec![self] } - other
+    }
+}
+
 /* Implement intersection between two Intervals */
 impl<T> BitAnd for &Interval<T>
 where
@@ -318,6 +322,73 @@ where
     }
 }
 
+impl<T> BitAnd for Interval<T>
+where
+    T: Copy + Clone + PartialEq + PartialOrd,
+{
+    type Output = Option<Interval<T>>;
+    fn bitand(self, other: Interval<T>) -> Option<Interval<T>> {
+        &self & &other
+    }
+}
+
+impl<T> BitAnd<IntervalCollection<T>> for Interval<T>
+where
+    T: Copy + Clone + PartialEq + PartialOrd,
+{
+    type Output = IntervalCollection<T>;
+    fn bitand(self, other: IntervalCollection<T>) -> IntervalCollection<T> {
+        &self & &other
+    }
+}
+
+impl<T> BitAnd<Interval<T>> for IntervalCollection<T>
+where
+    T: Copy + Clone + PartialEq + PartialOrd,
+{
+    type Output = IntervalCollection<T>;
+    fn bitand(self, other: Interval<T>) -> IntervalCollection<T> {
+        &self & &other
+    }
+}
+
+impl<T> BitAnd for IntervalCollection<T>
+where
+    T: Copy + Clone + PartialEq + PartialOrd,
+{
+    type Output = IntervalCollection<T>;
+    fn bitand(self, other: IntervalCollection<T>) -> IntervalCollection<T> {
+        &self & &other
+    }
+}
+
+/* Reflect an Interval around zero, swapping bounds to keep start <= stop */
+impl<T> Neg for Interval<T>
+where
+    T: Neg<Output = T>,
+{
+    type Output = Interval<T>;
+    fn neg(self) -> Interval<T> {
+        Interval {
+            start: -self.stop,
+            stop: -self.start,
+        }
+    }
+}
+
+/* Reflect all elements around zero, reversing their order */
+impl<T> Neg for IntervalCollection<T>
+where
+    T: Neg<Output = T>,
+{
+    type Output = IntervalCollection<T>;
+    fn neg(self) -> IntervalCollection<T> {
+        IntervalCollection {
+            elts: self.elts.into_iter().rev().map(|elt| -elt).collect(),
+        }
+    }
+}
+
 impl<T, Delta> Interval<T>
 where
     T: Sub<T, Output = Delta> + Add<Delta, Output = T> + Copy,
@@ -332,6 +403,58 @@ where
             stop: self.stop + delta,
         }
     }
+    /// Scales both bounds relative to pivot by factor
+    ///
+    /// On top of the bounds for shift, this requires Delta: Mul<F>, e.g.
+    /// f64 intervals scaled by f64, or chrono timestamps (Duration) scaled
+    /// by i32. A negative factor also reflects the interval around pivot:
+    /// bounds are then swapped to keep start <= stop, as with Neg.
+    pub fn scale<F>(&self, factor: F, pivot: T) -> Interval<T>
+    where
+        T: PartialOrd,
+        F: Copy,
+        Delta: Mul<F, Output = Delta>,
+    {
+        let start = pivot + (self.start - pivot) * factor;
+        let stop = pivot + (self.stop - pivot) * factor;
+        match start <= stop {
+            true => Interval { start, stop },
+            false => Interval {
+                start: stop,
+                stop: start,
+            },
+        }
+    }
+}
+
+impl<T, Delta> IntervalCollection<T>
+where
+    T: Sub<T, Output = Delta> + Add<Delta, Output = T> + Copy,
+    Delta: Copy,
+{
+    /// Durations of all elements, in order
+    pub fn durations(&self) -> impl Iterator<Item = Delta> + '_ {
+        self.elts.iter().map(|elt| elt.duration())
+    }
+
+    /// Scales all elements relative to pivot, see Interval::scale
+    ///
+    /// Elements keep their original order, even if a negative factor
+    /// reflects them.
+    pub fn scale<F>(&self, factor: F, pivot: T) -> IntervalCollection<T>
+    where
+        T: PartialOrd,
+        F: Copy,
+        Delta: Mul<F, Output = Delta>,
+    {
+        IntervalCollection {
+            elts: self
+                .elts
+                .iter()
+                .map(|elt| elt.scale(factor, pivot))
+                .collect(),
+        }
+    }
 }
 
 impl<T> Interval<T>
@@ -341,6 +464,217 @@ where
     pub fn overlap(&self, other: &Interval<T>) -> bool {
         self.start < other.stop && self.stop > other.start
     }
+
+    pub fn contains_interval(&self, other: &Interval<T>) -> bool {
+        self.start <= other.start && other.stop <= self.stop
+    }
+
+    pub fn overlaps_any(&self, collection: &IntervalCollection<T>) -> bool {
+        collection.overlaps(self)
+    }
+}
+
+impl<T> IntervalCollection<T>
+where
+    T: PartialOrd,
+{
+    /// True if any element overlaps iv, without building the intersection
+    pub fn overlaps(&self, iv: &Interval<T>) -> bool {
+        self.elts.iter().any(|elt| elt.overlap(iv))
+    }
+
+    /// Sorts elements by start then stop, without merging them unlike
+    /// normalize
+    pub fn sort(&mut self) {
+        self.elts.sort_by(|a, b| {
+            (&a.start, &a.stop)
+                .partial_cmp(&(&b.start, &b.stop))
+                .unwrap_or(Ordering::Equal)
+        })
+    }
+}
+
+impl<T> Interval<T>
+where
+    T: Copy + PartialOrd,
+{
+    /// Restricts the interval to bounds, None if they do not overlap
+    ///
+    /// Takes self by value so that it is not shadowed by Ord::clamp.
+    pub fn clamp(self, bounds: &Interval<T>) -> Option<Interval<T>> {
+        &self & bounds
+    }
+
+    /// Same remaining parts as the - operator, together with the removed
+    /// overlap, None if the intervals do not intersect
+    pub fn difference_parts(
+        &self,
+        other: &Interval<T>,
+    ) -> (IntervalCollection<T>, Option<Interval<T>>) {
+        let (left, _) = self.split_at(other.start);
+        let (_, right) = self.split_at(other.stop);
+        let remaining = IntervalCollection {
+            elts: left.into_iter().chain(right).collect(),
+        };
+        (remaining, self.clamp(other))
+    }
+
+    /// Cuts the interval at point into its left and right parts, None on a
+    /// side where point lies at or beyond the boundary
+    pub fn split_at(&self, point: T) -> (Option<Interval<T>>, Option<Interval<T>>) {
+        if point <= self.start {
+            return (None, Some(*self));
+        }
+        if point >= self.stop {
+            return (Some(*self), None);
+        }
+        (
+            Some(Interval {
+                start: self.start,
+                stop: point,
+            }),
+            Some(Interval {
+                start: point,
+                stop: self.stop,
+            }),
+        )
+    }
+}
+
+impl<T> IntervalCollection<T>
+where
+    T: Copy + PartialOrd,
+{
+    /// Restricts each element to bounds, dropping disjoint elements and
+    /// keeping the original order
+    pub fn clamp(&self, bounds: &Interval<T>) -> IntervalCollection<T> {
+        IntervalCollection {
+            elts: self
+                .elts
+                .iter()
+                .filter_map(|elt| elt.clamp(bounds))
+                .collect(),
+        }
+    }
+
+    /// Keeps the parts of all elements after t, i.e. clamp with no upper bound
+    pub fn since(&self, t: T) -> IntervalCollection<T> {
+        IntervalCollection {
+            elts: self
+                .elts
+                .iter()
+                .filter_map(|elt| elt.split_at(t).1)
+                .collect(),
+        }
+    }
+
+    /// Keeps the parts of all elements before t, i.e. clamp with no lower bound
+    pub fn until(&self, t: T) -> IntervalCollection<T> {
+        IntervalCollection {
+            elts: self
+                .elts
+                .iter()
+                .filter_map(|elt| elt.split_at(t).0)
+                .collect(),
+        }
+    }
+
+    /// Cuts all elements at point into the parts before and after it
+    pub fn split_at(&self, point: T) -> (IntervalCollection<T>, IntervalCollection<T>) {
+        let mut left = Vec::new();
+        let mut right = Vec::new();
+        for elt in &self.elts {
+            let (l, r) = elt.split_at(point);
+            left.extend(l);
+            right.extend(r);
+        }
+        (
+            IntervalCollection { elts: left },
+            IntervalCollection { elts: right },
+        )
+    }
+}
+
+impl<T> IntervalCollection<T>
+where
+    T: Ord + Copy,
+{
+    /// Returns the sorted union of all elements, merging overlapping and
+    /// touching intervals like the + operator does
+    pub fn normalize(&self) -> IntervalCollection<T> {
+        self.normalize_with(MergePolicy::Touching)
+    }
+
+    /// Returns the sorted union of all elements, merging touching intervals
+    /// or not depending on policy
+    pub fn normalize_with(&self, policy: MergePolicy) -> IntervalCollection<T> {
+        let mut sorted = self.elts.clone();
+        sorted.sort();
+        IntervalCollection {
+            elts: merge_sorted(&sorted, policy),
+        }
+    }
+
+    /// Union with other, only merging strictly overlapping intervals so that
+    /// adjacent ones remain separate occurrences
+    pub fn union_disjoint(&self, other: &IntervalCollection<T>) -> IntervalCollection<T> {
+        IntervalCollection {
+            elts: self.elts.iter().chain(&other.elts).copied().collect(),
+        }
+        .normalize_with(MergePolicy::Overlapping)
+    }
+
+    /// Returns the interval from the earliest start to the latest stop,
+    /// ignoring gaps in between, None for an empty collection
+    pub fn hull(&self) -> Option<Interval<T>> {
+        let start = self.elts.iter().map(|elt| elt.start).min()?;
+        let stop = self.elts.iter().map(|elt| elt.stop).max()?;
+        Some(Interval { start, stop })
+    }
+
+    /// Returns an empty (hence normalized) collection to insert into
+    pub fn builder() -> IntervalCollection<T> {
+        IntervalCollection { elts: Vec::new() }
+    }
+
+    /// Merges iv into a normalized collection so that it stays normalized, in
+    /// O(n)
+    pub fn insert(&mut self, iv: Interval<T>) {
+        let lo = self.elts.partition_point(|elt| elt.stop < iv.start);
+        let hi = lo + self.elts[lo..].partition_point(|elt| elt.start <= iv.stop);
+        let mut merged = iv;
+        if lo < hi {
+            merged.start = merged.start.min(self.elts[lo].start);
+            merged.stop = merged.stop.max(self.elts[hi - 1].stop);
+        }
+        self.elts.splice(lo..hi, [merged]);
+    }
+
+    /// True if the union of all elements contains the whole interval
+    pub fn covers(&self, other: &Interval<T>) -> bool {
+        self.normalize()
+            .elts
+            .iter()
+            .any(|elt| elt.contains_interval(other))
+    }
+
+    /// Intersects the collection with each window, as the & operator would,
+    /// indexing the elements once in an IntervalTree
+    ///
+    /// Each resulting collection is sorted by start.
+    pub fn intersect_all(&self, windows: &[Interval<T>]) -> Vec<IntervalCollection<T>> {
+        let tree = IntervalTree::new(self);
+        windows
+            .iter()
+            .map(|window| IntervalCollection {
+                elts: tree
+                    .query(*window)
+                    .into_iter()
+                    .filter_map(|elt| elt & window)
+                    .collect(),
+            })
+            .collect()
+    }
 }
 
 impl<T, Delta> IntervalCollection<T>
@@ -348,89 +682,511 @@ where
     T: Sub<T, Output = Delta> + Add<Delta, Output = T> + Copy + PartialOrd,
     Delta: Copy + Sum,
 {
+    /// Sum of the durations of all elements, counting overlaps several times:
+    /// see covered_duration for the duration of their union
     pub fn total_duration(&self) -> Delta {
-        self.elts.iter().map(|elt| elt.duration()).sum()
+        self.durations().sum()
     }
 }
 
-#[cfg(test)]
-mod tests {
+impl<T, Delta> IntervalCollection<T>
+where
+    T: Sub<T, Output = Delta> + Add<Delta, Output = T> + Copy + Ord,
+    Delta: Copy + Sum,
+{
+    /// Duration of the union of all elements, so that overlaps count once
+    pub fn covered_duration(&self) -> Delta {
+        self.normalize().total_duration()
+    }
 
-    use super::Interval;
-    use chrono::{DateTime, Duration, Utc};
+    /// Uncovered duration between the first start and the last stop, i.e.
+    /// the span of the collection minus the duration of its normalized union
+    pub fn total_gap(&self) -> Delta {
+        self.normalize()
+            .elts
+            .windows(2)
+            .map(|pair| pair[1].start - pair[0].stop)
+            .sum()
+    }
+}
 
-    static I1: Interval<i32> = Interval { start: 0, stop: 1 };
-    static I2: Interval<i32> = Interval { start: 1, stop: 2 };
-    static I3: Interval<i32> = Interval { start: 2, stop: 3 };
-    static I4: Interval<i32> = Interval { start: 3, stop: 4 };
-    static I5: Interval<i32> = Interval { start: 4, stop: 5 };
+impl<T, Delta> IntervalCollection<T>
+where
+    T: Sub<T, Output = Delta> + Copy + PartialOrd,
+    Delta: PartialOrd,
+{
+    /// Returns the element closest to point (at distance zero if it contains
+    /// point), None for an empty collection
+    ///
+    /// Ties are won by the element appearing first in the collection, i.e.
+    /// the one before point if the collection is sorted.
+    pub fn nearest(&self, point: T) -> Option<&Interval<T>> {
+        let mut res: Option<(&Interval<T>, Delta)> = None;
+        for elt in &self.elts {
+            let d = if point < elt.start {
+                elt.start - point
+            } else if point > elt.stop {
+                point - elt.stop
+            } else {
+                return Some(elt);
+            };
+            match &res {
+                Some((_, best)) if *best <= d => (),
+                _ => res = Some((elt, d)),
+            }
+        }
+        res.map(|(elt, _)| elt)
+    }
+}
 
-    #[test]
-    fn interval_i32() {
-        assert_eq!(I1.duration(), 1);
-        let shifted = I1.shift(1);
-        assert_eq!(shifted.duration(), 1);
-        assert_ne!(shifted, I1);
-        assert_eq!(shifted, I2);
-        assert_eq!(format!("{:?}", &shifted), "Interval { start: 1, stop: 2 }");
-        assert_eq!(format!("{:}", &shifted), "[1, 2]");
+impl<T> IntervalCollection<T> {
+    /// Builds a collection from (start, stop) pairs, as is: call normalize()
+    /// to sort and merge them
+    pub fn from_tuples(pairs: impl IntoIterator<Item = (T, T)>) -> IntervalCollection<T> {
+        IntervalCollection {
+            elts: pairs.into_iter().map(Interval::from).collect(),
+        }
     }
 
-    #[test]
-    fn interval_dt() {
-        let i_dt = Interval {
-            start: "2024-01-20T12:00:00Z"
-                .parse::<DateTime<Utc>>()
-                .expect("error date"),
-            stop: "2024-01-20T13:00:00Z"
-                .parse::<DateTime<Utc>>()
-                .expect("error date"),
-        };
-        assert_eq!(i_dt.duration(), Duration::hours(1));
-        assert_eq!(i_dt.shift(Duration::days(1)).duration(), Duration::hours(1));
+    pub fn retain(&mut self, f: impl Fn(&Interval<T>) -> bool) {
+        self.elts.retain(f)
     }
-    #[test]
-    fn intervals_consistent() {
-        assert_eq!(
-            format!("{:?}", I1 + I2),
-            "IntervalCollection { elts: [Interval { start: 0, stop: 2 }] }"
-        );
-        assert_eq!(format!("{:}", &(I1 + I2)), "[[0, 2]]");
-        assert_eq!(format!("{:}", &(I1 + I3)), "[[0, 1], [2, 3]]");
-        assert_eq!(format!("{:}", &(I2 + I4)), "[[1, 2], [3, 4]]");
-        let s1 = (I1 + I3) + (I2 + I4);
-        assert_eq!(format!("{:}", &s1), "[[0, 4]]");
-        let s2 = (I1 + I3) + (I4 + I5);
-        assert_eq!(format!("{:}", &s2), "[[0, 1], [2, 5]]");
-        let s3 = I1 + I3 + I4 + I5;
-        assert_eq!(format!("{:}", &s3), "[[0, 1], [2, 5]]");
+}
 
-        let i1 = Interval {
-            start: "2024-01-20T12:00:00Z"
-                .parse::<DateTime<Utc>>()
-                .expect("error date"),
-            stop: "2024-01-20T13:00:00Z"
-                .parse::<DateTime<Utc>>()
-                .expect("error date"),
-        };
-        let i2 = Interval {
-            start: "2024-01-20T13:00:00Z"
-                .parse::<DateTime<Utc>>()
-                .expect("error date"),
-            stop: "2024-01-20T14:00:00Z"
-                .parse::<DateTime<Utc>>()
-                .expect("error date"),
-        };
-        assert_eq!(
-            format!("{:}", &(&i1 + &i2)),
-            "[[2024-01-20 12:00:00 UTC, 2024-01-20 14:00:00 UTC]]"
-        );
-        assert_eq!((&i1 + &i2).total_duration(), Duration::hours(2));
+impl<T, Delta> IntervalCollection<T>
+where
+    T: Sub<T, Output = Delta> + Add<Delta, Output = T> + Copy,
+    Delta: Copy + PartialOrd,
+{
+    /// Drops elements shorter than min
+    pub fn filter_min_duration(mut self, min: Delta) -> IntervalCollection<T> {
+        self.retain(|elt| elt.duration() >= min);
+        self
     }
+}
 
-    #[test]
-    fn intervals_sub() {
-        assert_eq!(format!("{:}", &(I1 - I2)), "[[0, 1]]");
+impl IntervalCollection<i64> {
+    /// Fraction of window covered by the union of all elements, within [0, 1]
+    ///
+    /// Returns 0 for an empty window.
+    pub fn coverage_ratio(&self, window: &Interval<i64>) -> f64 {
+        let length = window.duration();
+        if length <= 0 {
+            return 0.;
+        }
+        let covered = self.normalize().clamp(window).total_duration();
+        (covered as f64 / length as f64).clamp(0., 1.)
+    }
+
+    /// Rasterizes the collection over [start, stop) into cells of width step,
+    /// true for cells overlapping any element
+    ///
+    /// The last cell is truncated at stop; the mask is empty if step is not
+    /// positive.
+    pub fn to_mask(&self, start: i64, stop: i64, step: i64) -> Vec<bool> {
+        if step <= 0 {
+            return Vec::new();
+        }
+        let normalized = self.normalize();
+        let mut elts = normalized.elts.iter().peekable();
+        let mut mask = Vec::new();
+        let mut lo = start;
+        while lo < stop {
+            let hi = lo.saturating_add(step).min(stop);
+            while elts.next_if(|elt| elt.stop <= lo).is_some() {}
+            mask.push(matches!(elts.peek(), Some(elt) if elt.start < hi));
+            lo = hi;
+        }
+        mask
+    }
+}
+
+impl<T, Delta> IntervalCollection<T>
+where
+    T: Sub<T, Output = Delta> + Copy + Ord,
+    Delta: PartialOrd,
+{
+    /// Sorts elements and merges consecutive ones separated by a gap of at
+    /// most max_gap
+    ///
+    /// With a zero max_gap, this is the same as normalize.
+    pub fn merge_within(mut self, max_gap: Delta) -> IntervalCollection<T> {
+        self.elts.sort();
+        let mut elts: Vec<Interval<T>> = Vec::with_capacity(self.elts.len());
+        for elt in self.elts {
+            match elts.last_mut() {
+                Some(last) if elt.start <= last.stop || elt.start - last.stop <= max_gap => {
+                    last.stop = last.stop.max(elt.stop)
+                }
+                _ => elts.push(elt),
+            }
+        }
+        IntervalCollection { elts }
+    }
+}
+
+/// Merges sorted intervals in a single sweep, depending on policy
+fn merge_sorted<T>(sorted: &[Interval<T>], policy: MergePolicy) -> Vec<Interval<T>>
+where
+    T: Ord + Copy,
+{
+    let mut elts: Vec<Interval<T>> = Vec::with_capacity(sorted.len());
+    for &elt in sorted {
+        match elts.last_mut() {
+            Some(last)
+                if elt.start < last.stop
+                    || (policy == MergePolicy::Touching && elt.start == last.stop) =>
+            {
+                last.stop = last.stop.max(elt.stop)
+            }
+            _ => elts.push(elt),
+        }
+    }
+    elts
+}
+
+/// Union of a slice of intervals, sorted in place then merged in one sweep
+///
+/// Overlapping and touching intervals are merged, as with normalize, without
+/// going through an IntervalCollection.
+pub fn union_intervals<T>(ivs: &mut [Interval<T>]) -> Vec<Interval<T>>
+where
+    T: Ord + Copy,
+{
+    ivs.sort_unstable();
+    merge_sorted(ivs, MergePolicy::Touching)
+}
+
+/// Union of many collections in O(n log n), by sorting all elements once and
+/// sweeping over them, rather than repeatedly applying the + operator which
+/// sorts again at each step
+pub fn union_many<T>(collections: &[&IntervalCollection<T>]) -> IntervalCollection<T>
+where
+    T: Ord + Copy,
+{
+    let mut elts: Vec<Interval<T>> = collections
+        .iter()
+        .flat_map(|collection| collection.elts.iter().copied())
+        .collect();
+    IntervalCollection {
+        elts: union_intervals(&mut elts),
+    }
+}
+
+/// Part of a union together with the labels of all sources covering it
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LabeledInterval<T, L> {
+    pub interval: Interval<T>,
+    pub labels: Vec<L>,
+}
+
+/// Sorted (position, is_start, index of the collection) boundaries of all
+/// non-empty elements, for sweeping over several collections at once
+///
+/// At equal positions, stops come first so that touching intervals do not
+/// overlap.
+fn boundary_events<'a, T>(
+    collections: impl Iterator<Item = &'a IntervalCollection<T>>,
+) -> Vec<(T, bool, usize)>
+where
+    T: Ord + Copy + 'a,
+{
+    let mut events: Vec<(T, bool, usize)> = Vec::new();
+    for (idx, collection) in collections.enumerate() {
+        for elt in collection.elts.iter().filter(|elt| elt.start < elt.stop) {
+            events.push((elt.start, true, idx));
+            events.push((elt.stop, false, idx));
+        }
+    }
+    events.sort();
+    events
+}
+
+/// Union of labeled collections, split at each boundary where the set of
+/// contributing labels changes
+///
+/// Each resulting element carries the sorted labels of the collections it
+/// comes from, e.g. A-only, A and B, then B-only parts for two overlapping
+/// intervals labeled A and B.
+pub fn union_labeled<T, L>(
+    collections: &[(L, &IntervalCollection<T>)],
+) -> Vec<LabeledInterval<T, L>>
+where
+    T: Ord + Copy,
+    L: Ord + Clone,
+{
+    let events = boundary_events(collections.iter().map(|(_, collection)| *collection));
+
+    let mut active = vec![0usize; collections.len()];
+    let mut res: Vec<LabeledInterval<T, L>> = Vec::new();
+    let mut i = 0;
+    while i < events.len() {
+        let position = events[i].0;
+        while i < events.len() && events[i].0 == position {
+            let (_, is_start, idx) = events[i];
+            match is_start {
+                true => active[idx] += 1,
+                false => active[idx] -= 1,
+            }
+            i += 1;
+        }
+        if i == events.len() {
+            break;
+        }
+        let mut labels: Vec<L> = active
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 0)
+            .map(|(idx, _)| collections[idx].0.clone())
+            .collect();
+        if labels.is_empty() {
+            continue;
+        }
+        labels.sort();
+        labels.dedup();
+        let next = events[i].0;
+        match res.last_mut() {
+            Some(last) if last.interval.stop == position && last.labels == labels => {
+                last.interval.stop = next
+            }
+            _ => res.push(LabeledInterval {
+                interval: Interval {
+                    start: position,
+                    stop: next,
+                },
+                labels,
+            }),
+        }
+    }
+    res
+}
+
+/// Returns the pairs of identifiers whose collections overlap in time
+///
+/// Sweeps over all sorted boundaries instead of intersecting all pairs of
+/// collections. Pairs are returned in the order of the input slice.
+pub fn overlapping_pairs<Id, T>(collections: &[(Id, IntervalCollection<T>)]) -> Vec<(Id, Id)>
+where
+    Id: Clone,
+    T: Ord + Copy,
+{
+    let events = boundary_events(collections.iter().map(|(_, collection)| collection));
+
+    let mut active: BTreeMap<usize, usize> = BTreeMap::new();
+    let mut pairs: BTreeSet<(usize, usize)> = BTreeSet::new();
+    for (_, is_start, idx) in events {
+        if is_start {
+            for &other in active.keys().filter(|&&other| other != idx) {
+                pairs.insert((other.min(idx), other.max(idx)));
+            }
+            *active.entry(idx).or_insert(0) += 1;
+        } else if let Some(count) = active.get_mut(&idx) {
+            *count -= 1;
+            if *count == 0 {
+                active.remove(&idx);
+            }
+        }
+    }
+    pairs
+        .into_iter()
+        .map(|(i, j)| (collections[i].0.clone(), collections[j].0.clone()))
+        .collect()
+}
+
+/// Static index over the elements of an IntervalCollection for overlap queries
+///
+/// Construction sorts a copy of the elements and augments each node of an
+/// implicit balanced tree with the maximum stop of its subtree: O(n log n)
+/// time and O(n) memory. Queries for k results then run in O(min(n, k log n)),
+/// as each result may cost its own root-to-leaf path when it sits among
+/// short intervals ending before the query.
+#[derive(Debug)]
+pub struct IntervalTree<T> {
+    elts: Vec<Interval<T>>,
+    max_stop: Vec<T>,
+}
+
+impl<T> IntervalTree<T>
+where
+    T: Ord + Copy,
+{
+    pub fn new(collection: &IntervalCollection<T>) -> IntervalTree<T> {
+        let mut elts = collection.elts.clone();
+        elts.sort();
+        let mut max_stop: Vec<T> = elts.iter().map(|elt| elt.stop).collect();
+        IntervalTree::<T>::build(&elts, &mut max_stop, 0, elts.len());
+        IntervalTree { elts, max_stop }
+    }
+
+    fn build(elts: &[Interval<T>], max_stop: &mut [T], lo: usize, hi: usize) -> Option<T> {
+        if lo >= hi {
+            return None;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let left = IntervalTree::<T>::build(elts, max_stop, lo, mid);
+        let right = IntervalTree::<T>::build(elts, max_stop, mid + 1, hi);
+        let stop = [left, right]
+            .into_iter()
+            .flatten()
+            .fold(elts[mid].stop, |acc, x| acc.max(x));
+        max_stop[mid] = stop;
+        Some(stop)
+    }
+
+    /// Returns all elements overlapping q, sorted by start
+    pub fn query(&self, q: Interval<T>) -> Vec<&Interval<T>> {
+        let mut res = Vec::new();
+        self.search(&q, 0, self.elts.len(), &mut res);
+        res
+    }
+
+    fn search<'a>(&'a self, q: &Interval<T>, lo: usize, hi: usize, res: &mut Vec<&'a Interval<T>>) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        if self.max_stop[mid] <= q.start {
+            return;
+        }
+        self.search(q, lo, mid, res);
+        let elt = &self.elts[mid];
+        if elt.start < q.stop {
+            if elt.overlap(q) {
+                res.push(elt);
+            }
+            self.search(q, mid + 1, hi, res);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.elts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elts.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{
+        overlapping_pairs, union_intervals, union_labeled, union_many, Interval,
+        IntervalCollection, IntervalTree, MergePolicy,
+    };
+    use chrono::{DateTime, Duration, Utc};
+    use std::collections::HashSet;
+
+    static I1: Interval<i32> = Interval { start: 0, stop: 1 };
+    static I2: Interval<i32> = Interval { start: 1, stop: 2 };
+    static I3: Interval<i32> = Interval { start: 2, stop: 3 };
+    static I4: Interval<i32> = Interval { start: 3, stop: 4 };
+    static I5: Interval<i32> = Interval { start: 4, stop: 5 };
+
+    /// Reproducible random intervals starting within [0, span), with a
+    /// duration within [0, max_len), from a linear congruential generator
+    fn random_intervals(mut seed: u64, n: usize, span: i64, max_len: i64) -> Vec<Interval<i64>> {
+        let mut random = |max: i64| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+            ((seed >> 33) % max as u64) as i64
+        };
+        (0..n)
+            .map(|_| {
+                let start = random(span);
+                Interval {
+                    start,
+                    stop: start + random(max_len),
+                }
+            })
+            .collect()
+    }
+
+    /// Reference union merging each interval with all those it overlaps or
+    /// touches, in quadratic time
+    fn naive_union(elts: &[Interval<i64>]) -> Vec<Interval<i64>> {
+        let mut res: Vec<Interval<i64>> = Vec::new();
+        for &elt in elts {
+            let mut merged = elt;
+            res.retain(|other| {
+                let touching = other.start <= merged.stop && merged.start <= other.stop;
+                if touching {
+                    merged.start = merged.start.min(other.start);
+                    merged.stop = merged.stop.max(other.stop);
+                }
+                !touching
+            });
+            res.push(merged);
+        }
+        res.sort();
+        res
+    }
+
+    #[test]
+    fn interval_i32() {
+        assert_eq!(I1.duration(), 1);
+        let shifted = I1.shift(1);
+        assert_eq!(shifted.duration(), 1);
+        assert_ne!(shifted, I1);
+        assert_eq!(shifted, I2);
+        assert_eq!(format!("{:?}", &shifted), "Interval { start: 1, stop: 2 }");
+        assert_eq!(format!("{:}", &shifted), "[1, 2]");
+    }
+
+    #[test]
+    fn interval_dt() {
+        let i_dt = Interval {
+            start: "2024-01-20T12:00:00Z"
+                .parse::<DateTime<Utc>>()
+                .expect("error date"),
+            stop: "2024-01-20T13:00:00Z"
+                .parse::<DateTime<Utc>>()
+                .expect("error date"),
+        };
+        assert_eq!(i_dt.duration(), Duration::hours(1));
+        assert_eq!(i_dt.shift(Duration::days(1)).duration(), Duration::hours(1));
+    }
+    #[test]
+    fn intervals_consistent() {
+        assert_eq!(
+            format!("{:?}", I1 + I2),
+            "IntervalCollection { elts: [Interval { start: 0, stop: 2 }] }"
+        );
+        assert_eq!(format!("{:}", &(I1 + I2)), "[[0, 2]]");
+        assert_eq!(format!("{:}", &(I1 + I3)), "[[0, 1], [2, 3]]");
+        assert_eq!(format!("{:}", &(I2 + I4)), "[[1, 2], [3, 4]]");
+        let s1 = (I1 + I3) + (I2 + I4);
+        assert_eq!(format!("{:}", &s1), "[[0, 4]]");
+        let s2 = (I1 + I3) + (I4 + I5);
+        assert_eq!(format!("{:}", &s2), "[[0, 1], [2, 5]]");
+        let s3 = I1 + I3 + I4 + I5;
+        assert_eq!(format!("{:}", &s3), "[[0, 1], [2, 5]]");
+
+        let i1 = Interval {
+            start: "2024-01-20T12:00:00Z"
+                .parse::<DateTime<Utc>>()
+                .expect("error date"),
+            stop: "2024-01-20T13:00:00Z"
+                .parse::<DateTime<Utc>>()
+                .expect("error date"),
+        };
+        let i2 = Interval {
+            start: "2024-01-20T13:00:00Z"
+                .parse::<DateTime<Utc>>()
+                .expect("error date"),
+            stop: "2024-01-20T14:00:00Z"
+                .parse::<DateTime<Utc>>()
+                .expect("error date"),
+        };
+        assert_eq!(
+            format!("{:}", &(&i1 + &i2)),
+            "[[2024-01-20 12:00:00 UTC, 2024-01-20 14:00:00 UTC]]"
+        );
+        assert_eq!((&i1 + &i2).total_duration(), Duration::hours(2));
+    }
+
+    #[test]
+    fn intervals_sub() {
+        assert_eq!(format!("{:}", &(I1 - I2)), "[[0, 1]]");
         assert_eq!(
             format!("{:}", &(Interval { start: 0, stop: 2 } - I2)),
             "[[0, 1]]"
@@ -441,5 +1197,672 @@ mod tests {
             format!("{:}", &(((I1 + I2) + (I2 + I3) + I5) - (I2 + I3))),
             "[[0, 1], [4, 5]]"
         );
+        assert_eq!(
+            format!("{:}", &(Interval { start: 0, stop: 10 } - (I2 + I4))),
+            "[[0, 1], [2, 3], [4, 10]]"
+        );
+    }
+
+    #[test]
+    fn intervals_xor() {
+        let left = Interval { start: 0, stop: 3 };
+        let right = Interval { start: 2, stop: 5 };
+        assert_eq!(format!("{:}", &(left ^ right)), "[[0, 2], [3, 5]]");
+        assert_eq!(format!("{:}", &(right ^ left)), "[[0, 2], [3, 5]]");
+        assert_eq!(format!("{:}", &(I3 ^ I1)), "[[0, 1], [2, 3]]");
+        assert_eq!(
+            format!("{:}", &(Interval { start: 0, stop: 3 } ^ I2)),
+            "[[0, 1], [2, 3]]"
+        );
+        assert_eq!(
+            format!("{:}", &(I1 ^ Interval { start: 0, stop: 2 })),
+            "[[1, 2]]"
+        );
+        assert!((I1 ^ I1).elts.is_empty());
+    }
+
+    #[test]
+    fn interval_tree() {
+        let collection = IntervalCollection {
+            elts: random_intervals(42, 1000, 10_000, 50),
+        };
+        let tree = IntervalTree::new(&collection);
+        assert_eq!(tree.len(), 1000);
+
+        for q in random_intervals(43, 100, 10_000, 200) {
+            let mut expected: Vec<&Interval<i64>> = collection
+                .elts
+                .iter()
+                .filter(|elt| elt.overlap(&q))
+                .collect();
+            expected.sort();
+            assert_eq!(tree.query(q), expected);
+
+            let mut clipped: Vec<Interval<i64>> =
+                tree.query(q).iter().filter_map(|elt| *elt & &q).collect();
+            let mut brute_force = (&collection & &q).elts;
+            clipped.sort();
+            brute_force.sort();
+            assert_eq!(clipped, brute_force);
+        }
+
+        let empty = IntervalTree::new(&IntervalCollection::<i64> { elts: vec![] });
+        assert!(empty.is_empty());
+        assert!(empty.query(Interval { start: 0, stop: 1 }).is_empty());
+    }
+
+    #[test]
+    fn intervals_clamp() {
+        let bounds = Interval { start: 1, stop: 4 };
+        assert_eq!(I2.clamp(&bounds), Some(I2));
+        assert_eq!(
+            Interval { start: 0, stop: 2 }.clamp(&bounds),
+            Some(Interval { start: 1, stop: 2 })
+        );
+        assert_eq!(I1.clamp(&bounds), None);
+        assert_eq!(I5.clamp(&bounds), None);
+
+        let collection = IntervalCollection {
+            elts: vec![
+                I5,
+                Interval { start: 3, stop: 6 },
+                I1,
+                Interval { start: 0, stop: 2 },
+            ],
+        };
+        assert_eq!(
+            format!("{:}", &collection.clamp(&bounds)),
+            "[[3, 4], [1, 2]]"
+        );
+    }
+
+    #[test]
+    fn intervals_split_at() {
+        let i = Interval { start: 0, stop: 10 };
+        assert_eq!(
+            i.split_at(4),
+            (
+                Some(Interval { start: 0, stop: 4 }),
+                Some(Interval { start: 4, stop: 10 })
+            )
+        );
+        assert_eq!(i.split_at(0), (None, Some(i)));
+        assert_eq!(i.split_at(-1), (None, Some(i)));
+        assert_eq!(i.split_at(10), (Some(i), None));
+        assert_eq!(i.split_at(11), (Some(i), None));
+
+        let (left, right) = (I1 + I3 + Interval { start: 4, stop: 6 }).split_at(5);
+        assert_eq!(format!("{:}", &left), "[[0, 1], [2, 3], [4, 5]]");
+        assert_eq!(format!("{:}", &right), "[[5, 6]]");
+    }
+
+    #[test]
+    fn interval_hash() {
+        let set: HashSet<Interval<i32>> = [I1, I2, I1, I1.shift(1)].into_iter().collect();
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&I2));
+        assert!(!set.contains(&I3));
+    }
+
+    #[test]
+    fn intervals_covers() {
+        assert!(I1.contains_interval(&I1));
+        assert!(Interval { start: 0, stop: 3 }.contains_interval(&I2));
+        assert!(!I1.contains_interval(&Interval { start: 0, stop: 2 }));
+        assert!(!I1.contains_interval(&I2));
+
+        let gap = I1 + I3;
+        assert!(gap.covers(&I1));
+        assert!(gap.covers(&Interval { start: 2, stop: 3 }));
+        assert!(!gap.covers(&Interval { start: 0, stop: 3 }));
+        assert!(!gap.covers(&I2));
+        assert!((I1 + I2 + I3).covers(&Interval { start: 0, stop: 3 }));
+
+        let overlapping = IntervalCollection {
+            elts: vec![
+                Interval { start: 1, stop: 3 },
+                Interval { start: 0, stop: 2 },
+            ],
+        };
+        assert!(overlapping.covers(&Interval { start: 0, stop: 3 }));
+        assert_eq!(format!("{:}", &overlapping.normalize()), "[[0, 3]]");
+        assert!(!IntervalCollection::<i32> { elts: vec![] }.covers(&I1));
+    }
+
+    #[test]
+    fn intervals_total_gap() {
+        assert_eq!((I1 + I3 + I5.shift(1)).total_gap(), 3);
+        assert_eq!((I1 + I2 + I3).total_gap(), 0);
+        assert_eq!((I3 + I1 + Interval { start: 0, stop: 2 }).total_gap(), 0);
+        assert_eq!(IntervalCollection::<i32> { elts: vec![I1] }.total_gap(), 0);
+        assert_eq!(IntervalCollection::<i32> { elts: vec![] }.total_gap(), 0);
+    }
+
+    #[test]
+    fn intervals_nearest() {
+        let collection = I1 + I4 + Interval { start: 8, stop: 9 };
+        assert_eq!(collection.nearest(0), Some(&I1));
+        assert_eq!(collection.nearest(4), Some(&I4));
+        assert_eq!(collection.nearest(-5), Some(&I1));
+        assert_eq!(
+            collection.nearest(12),
+            Some(&Interval { start: 8, stop: 9 })
+        );
+        assert_eq!(collection.nearest(5), Some(&I4));
+        assert_eq!(collection.nearest(7), Some(&Interval { start: 8, stop: 9 }));
+        // equidistant from [0, 1] and [3, 4]: the earlier one wins
+        assert_eq!(collection.nearest(2), Some(&I1));
+        assert_eq!(IntervalCollection::<i32> { elts: vec![] }.nearest(0), None);
+    }
+
+    #[test]
+    fn intervals_filter() {
+        let mut collection = IntervalCollection {
+            elts: vec![
+                I1,
+                Interval { start: 5, stop: 5 },
+                Interval { start: 6, stop: 9 },
+            ],
+        };
+        assert_eq!(
+            format!("{:}", &collection.filter_min_duration(1)),
+            "[[0, 1], [6, 9]]"
+        );
+
+        collection = IntervalCollection {
+            elts: vec![I1, I2, Interval { start: 6, stop: 9 }],
+        };
+        collection.retain(|elt| elt.start > 0);
+        assert_eq!(format!("{:}", &collection), "[[1, 2], [6, 9]]");
+        assert_eq!(
+            format!("{:}", &collection.filter_min_duration(2)),
+            "[[6, 9]]"
+        );
+    }
+
+    #[test]
+    fn intervals_insert() {
+        let mut collection = IntervalCollection::builder();
+        collection.insert(I5);
+        collection.insert(I1);
+        assert_eq!(format!("{:}", &collection), "[[0, 1], [4, 5]]");
+        collection.insert(I3);
+        assert_eq!(format!("{:}", &collection), "[[0, 1], [2, 3], [4, 5]]");
+        collection.insert(Interval { start: 7, stop: 8 });
+        collection.insert(I4);
+        assert_eq!(format!("{:}", &collection), "[[0, 1], [2, 5], [7, 8]]");
+        collection.insert(Interval {
+            start: -1,
+            stop: 10,
+        });
+        assert_eq!(format!("{:}", &collection), "[[-1, 10]]");
+
+        let shuffled = [I4, I1, Interval { start: 7, stop: 9 }, I5, I2];
+        let mut collection = IntervalCollection::builder();
+        for elt in shuffled {
+            collection.insert(elt);
+        }
+        let expected = IntervalCollection {
+            elts: shuffled.to_vec(),
+        }
+        .normalize();
+        assert_eq!(collection.elts, expected.elts);
+    }
+
+    #[test]
+    fn intervals_from_tuples() {
+        assert_eq!(Interval::from((0, 1)), I1);
+        let i: Interval<i32> = (1, 2).into();
+        assert_eq!(i, I2);
+
+        let collection = IntervalCollection::from_tuples([(0, 1), (2, 3)]);
+        assert_eq!(collection.elts, vec![I1, I3]);
+        let collection = IntervalCollection::from_tuples(vec![(2, 3), (0, 2)]);
+        assert_eq!(format!("{:}", &collection), "[[2, 3], [0, 2]]");
+        assert_eq!(format!("{:}", &collection.normalize()), "[[0, 3]]");
+    }
+
+    #[test]
+    fn intervals_durations() {
+        let collection = I1 + Interval { start: 2, stop: 5 };
+        assert_eq!(collection.durations().collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(collection.durations().max(), Some(3));
+    }
+
+    #[test]
+    fn intervals_and() {
+        assert_eq!(I1 & I2, None);
+        assert_eq!(Interval { start: 0, stop: 2 } & I2, Some(I2));
+        assert_eq!(
+            format!("{:}", &(Interval { start: 0, stop: 3 } & (I1 + I3 + I5))),
+            "[[0, 1], [2, 3]]"
+        );
+        assert_eq!(
+            format!("{:}", &((I1 + I3 + I5) & Interval { start: 0, stop: 3 })),
+            "[[0, 1], [2, 3]]"
+        );
+        assert_eq!(
+            format!("{:}", &((I1 + I3) & (Interval { start: 0, stop: 5 } + I5))),
+            "[[0, 1], [2, 3]]"
+        );
+    }
+
+    #[test]
+    fn intervals_overlaps() {
+        let collection = I1 + I3 + I5;
+        assert!(collection.overlaps(&Interval { start: 1, stop: 3 }));
+        assert!(Interval { start: 1, stop: 3 }.overlaps_any(&collection));
+        assert!(!collection.overlaps(&I2));
+        assert!(!I4.overlaps_any(&collection));
+        assert!(!I1.overlaps_any(&IntervalCollection { elts: vec![] }));
+    }
+
+    #[test]
+    fn intervals_empty() {
+        let empty = IntervalCollection::<i32> { elts: vec![] };
+        assert_eq!(format!("{:}", &empty), "∅");
+        assert_eq!(format!("{:?}", &empty), "IntervalCollection { elts: [] }");
+        assert_eq!(format!("{:}", &(I1 - I1)), "∅");
+        assert_eq!(format!("{:}", &(I1 & (I3 + I5))), "∅");
+    }
+
+    #[test]
+    fn intervals_hull() {
+        let collection = I1 + Interval { start: 5, stop: 6 };
+        assert_eq!(collection.hull(), Some(Interval { start: 0, stop: 6 }));
+        let unsorted = IntervalCollection {
+            elts: vec![I3, Interval { start: 0, stop: 5 }, I2],
+        };
+        assert_eq!(unsorted.hull(), Some(Interval { start: 0, stop: 5 }));
+        assert_eq!(IntervalCollection::<i32> { elts: vec![] }.hull(), None);
+    }
+
+    #[test]
+    fn intervals_union_many() {
+        let mut collections = Vec::new();
+        for seed in 0..5 {
+            collections.push(IntervalCollection {
+                elts: random_intervals(seed, 200, 5_000, 20),
+            });
+        }
+        let refs: Vec<&IntervalCollection<i64>> = collections.iter().collect();
+        let fast = union_many(&refs);
+
+        let all: Vec<Interval<i64>> = collections
+            .iter()
+            .flat_map(|collection| collection.elts.iter().copied())
+            .collect();
+        assert_eq!(fast.elts, naive_union(&all));
+
+        // output of the former + operator, chained over these sorted inputs
+        let small: Vec<IntervalCollection<i64>> = (0..3)
+            .map(|seed| {
+                let mut elts = random_intervals(seed, 8, 100, 10);
+                elts.sort();
+                IntervalCollection { elts }
+            })
+            .collect();
+        assert_eq!(
+            format!("{}", &union_many(&[&small[0], &small[1], &small[2]])),
+            "[[0, 8], [14, 16], [21, 27], [28, 39], [43, 44], [50, 71], [76, 88], [95, 108]]"
+        );
+
+        assert_eq!(
+            format!("{}", &union_many(&[&(I1 + I3), &(I2 + I5)])),
+            "[[0, 3], [4, 5]]"
+        );
+        assert!(union_many::<i32>(&[]).elts.is_empty());
+    }
+
+    #[test]
+    fn intervals_scale() {
+        let i = Interval {
+            start: 2.0,
+            stop: 4.0,
+        };
+        assert_eq!(
+            i.scale(2.0, 2.0),
+            Interval {
+                start: 2.0,
+                stop: 6.0
+            }
+        );
+        assert_eq!(
+            i.scale(0.5, 0.0),
+            Interval {
+                start: 1.0,
+                stop: 2.0
+            }
+        );
+        assert_eq!(I2.scale(2, 0), Interval { start: 2, stop: 4 });
+
+        let collection = (I1 + I3).scale(2, 1);
+        assert_eq!(format!("{:}", &collection), "[[-1, 1], [3, 5]]");
+
+        // negative factors reflect around the pivot, keeping start <= stop
+        assert_eq!(
+            I2.scale(-2, 0),
+            Interval {
+                start: -4,
+                stop: -2
+            }
+        );
+        assert_eq!(I2.scale(-1, 0), -I2);
+        assert_eq!(
+            i.scale(-0.5, 2.0),
+            Interval {
+                start: 1.0,
+                stop: 2.0
+            }
+        );
+        let collection = (I1 + I3).scale(-1, 0);
+        assert_eq!(format!("{:}", &collection), "[[-1, 0], [-3, -2]]");
+        assert!(collection.elts.iter().all(|elt| elt.start <= elt.stop));
+
+        // chrono timestamps scale through their Duration
+        let noon = "2024-01-20T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let i_dt = Interval {
+            start: noon,
+            stop: noon + Duration::hours(1),
+        };
+        assert_eq!(i_dt.scale(3, i_dt.start).duration(), Duration::hours(3));
+        assert_eq!(i_dt.scale(-1, noon).stop, noon);
+    }
+
+    #[test]
+    fn intervals_union_labeled() {
+        let a = IntervalCollection {
+            elts: vec![Interval { start: 0, stop: 3 }],
+        };
+        let b = IntervalCollection {
+            elts: vec![
+                Interval { start: 2, stop: 5 },
+                Interval { start: 7, stop: 8 },
+            ],
+        };
+        let res = union_labeled(&[("A", &a), ("B", &b)]);
+        let summary: Vec<String> = res
+            .iter()
+            .map(|elt| format!("{} {:?}", &elt.interval, elt.labels))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                "[0, 2] [\"A\"]",
+                "[2, 3] [\"A\", \"B\"]",
+                "[3, 5] [\"B\"]",
+                "[7, 8] [\"B\"]",
+            ]
+        );
+
+        // touching elements from the same source are merged
+        let res = union_labeled(&[("A", &(I1 + I2)), ("B", &(I2 + I3))]);
+        assert_eq!(res.len(), 3);
+        assert_eq!(res[0].interval, I1);
+        assert_eq!(res[1].labels, vec!["A", "B"]);
+        assert_eq!(res[2].interval, I3);
+        assert!(union_labeled::<i32, &str>(&[]).is_empty());
+    }
+
+    #[test]
+    fn intervals_overlapping_pairs() {
+        let flights = vec![
+            (
+                "AFR123",
+                IntervalCollection::from_tuples([(0i64, 10), (20, 30)]),
+            ),
+            ("BAW456", IntervalCollection::from_tuples([(10, 20)])),
+            ("DLH789", IntervalCollection::from_tuples([(25, 40)])),
+        ];
+        assert_eq!(overlapping_pairs(&flights), vec![("AFR123", "DLH789")]);
+
+        let flights = vec![
+            (1, IntervalCollection::from_tuples([(0i64, 10)])),
+            (2, IntervalCollection::from_tuples([(5, 6), (8, 9)])),
+            (3, IntervalCollection::from_tuples([(9, 12)])),
+        ];
+        assert_eq!(overlapping_pairs(&flights), vec![(1, 2), (1, 3)]);
+    }
+
+    #[test]
+    fn intervals_coverage_ratio() {
+        let window = Interval { start: 0, stop: 10 };
+        let coll = IntervalCollection::from_tuples([(2i64, 4)]);
+        assert_eq!(coll.coverage_ratio(&window), 0.2);
+
+        let coll = IntervalCollection::from_tuples([(-5i64, 3), (2, 4), (8, 20)]);
+        assert_eq!(coll.coverage_ratio(&window), 0.6);
+        let coll = IntervalCollection::from_tuples([(-5i64, 20)]);
+        assert_eq!(coll.coverage_ratio(&window), 1.);
+        assert_eq!(IntervalCollection::builder().coverage_ratio(&window), 0.);
+        assert_eq!(coll.coverage_ratio(&Interval { start: 5, stop: 5 }), 0.);
+    }
+
+    #[test]
+    fn intervals_sort() {
+        let mut coll = IntervalCollection {
+            elts: vec![I3, I1, I2],
+        };
+        coll.sort();
+        assert_eq!(format!("{}", &coll), "[[0, 1], [1, 2], [2, 3]]");
+
+        let mut coll = IntervalCollection::from_tuples([(2., 5.), (0., 3.), (0., 1.), (1., 4.)]);
+        coll.sort();
+        assert_eq!(coll.elts.len(), 4);
+        assert_eq!(format!("{}", &coll), "[[0, 1], [0, 3], [1, 4], [2, 5]]");
+    }
+
+    #[test]
+    fn intervals_difference_parts() {
+        let iv = Interval { start: 0, stop: 10 };
+        let (remaining, removed) = iv.difference_parts(&Interval { start: 3, stop: 5 });
+        assert_eq!(format!("{}", &remaining), "[[0, 3], [5, 10]]");
+        assert_eq!(removed, Some(Interval { start: 3, stop: 5 }));
+
+        for other in [I1, I2, I3, Interval { start: -1, stop: 0 }] {
+            let (remaining, removed) = I2.difference_parts(&other);
+            assert_eq!(format!("{}", &remaining), format!("{}", &(I2 - other)));
+            assert_eq!(removed, I2 & other);
+        }
+        let (remaining, removed) = I2.difference_parts(&Interval { start: 0, stop: 3 });
+        assert!(remaining.elts.is_empty());
+        assert_eq!(removed, Some(I2));
+    }
+
+    #[test]
+    fn intervals_to_mask() {
+        let coll = IntervalCollection::from_tuples([(4i64, 6), (0, 2)]);
+        assert_eq!(
+            coll.to_mask(0, 6, 1),
+            vec![true, true, false, false, true, true]
+        );
+        assert_eq!(coll.to_mask(0, 7, 2), vec![true, false, true, false]);
+        assert_eq!(coll.to_mask(1, 5, 3), vec![true, true]);
+        assert!(coll.to_mask(0, 6, 0).is_empty());
+        assert_eq!(
+            IntervalCollection::builder().to_mask(0, 3, 1),
+            vec![false; 3]
+        );
+
+        // 5 s steps in nanoseconds, above u32::MAX
+        let step = 5_000_000_000;
+        let coll = IntervalCollection::from_tuples([(0i64, step), (12 * step / 5, 13 * step / 5)]);
+        assert_eq!(
+            coll.to_mask(0, 4 * step, step),
+            vec![true, false, true, false]
+        );
+
+        let coll = IntervalCollection::from_tuples([(i64::MAX - 2, i64::MAX)]);
+        assert_eq!(
+            coll.to_mask(i64::MAX - 10, i64::MAX, 4),
+            vec![false, false, true]
+        );
+    }
+
+    #[test]
+    fn intervals_merge_within() {
+        let coll = IntervalCollection::from_tuples([(2, 3), (0, 1)]);
+        assert_eq!(format!("{}", &coll.merge_within(1)), "[[0, 3]]");
+        let coll = IntervalCollection::from_tuples([(2, 3), (0, 1)]);
+        assert_eq!(format!("{}", &coll.merge_within(0)), "[[0, 1], [2, 3]]");
+
+        let coll = IntervalCollection {
+            elts: vec![I4, I1, I2],
+        };
+        assert_eq!(format!("{}", &coll.merge_within(0)), "[[0, 2], [3, 4]]");
+
+        let now = chrono::Utc::now();
+        let minutes = |m| now + chrono::Duration::minutes(m);
+        let coll = IntervalCollection::from_tuples([
+            (minutes(0), minutes(10)),
+            (minutes(11), minutes(20)),
+            (minutes(30), minutes(40)),
+        ]);
+        let merged = coll.merge_within(chrono::Duration::minutes(2));
+        assert_eq!(merged.elts.len(), 2);
+        assert_eq!(merged.elts[0], (minutes(0), minutes(20)).into());
+    }
+
+    #[test]
+    fn intervals_covered_duration() {
+        let coll = IntervalCollection::from_tuples([(0, 2), (1, 3)]);
+        assert_eq!(coll.covered_duration(), 3);
+        assert_eq!(coll.total_duration(), 4);
+
+        let coll = IntervalCollection {
+            elts: vec![I4, I1, I2, I1],
+        };
+        assert_eq!(coll.covered_duration(), 3);
+        assert_eq!(coll.total_duration(), 4);
+    }
+
+    #[test]
+    fn intervals_merge_policy() {
+        let left = IntervalCollection { elts: vec![I1] };
+        let right = IntervalCollection { elts: vec![I2] };
+        assert_eq!(format!("{}", &(&left + &right)), "[[0, 2]]");
+        assert_eq!(
+            format!("{}", &left.union_disjoint(&right)),
+            "[[0, 1], [1, 2]]"
+        );
+
+        let coll = IntervalCollection {
+            elts: vec![I2, I1, Interval { start: 0, stop: 2 }, I4],
+        };
+        assert_eq!(
+            format!("{}", &coll.normalize_with(MergePolicy::Touching)),
+            "[[0, 2], [3, 4]]"
+        );
+        assert_eq!(
+            format!("{}", &coll.normalize_with(MergePolicy::Overlapping)),
+            "[[0, 2], [3, 4]]"
+        );
+        let coll = IntervalCollection {
+            elts: vec![I3, I1, I2],
+        };
+        assert_eq!(
+            format!("{}", &coll.normalize_with(MergePolicy::Overlapping)),
+            "[[0, 1], [1, 2], [2, 3]]"
+        );
+    }
+
+    #[test]
+    fn intervals_intersect_all() {
+        let coverage = IntervalCollection::from_tuples([(6, 12), (0, 4)]);
+        let windows = [
+            Interval { start: 2, stop: 8 },
+            Interval { start: 4, stop: 6 },
+            Interval {
+                start: 10,
+                stop: 20,
+            },
+        ];
+        let res = coverage.intersect_all(&windows);
+        assert_eq!(res.len(), 3);
+        assert_eq!(format!("{}", &res[0]), "[[2, 4], [6, 8]]");
+        assert_eq!(format!("{}", &res[1]), "∅");
+        assert_eq!(format!("{}", &res[2]), "[[10, 12]]");
+
+        for (window, res) in windows.iter().zip(&res) {
+            let expected = &coverage & window;
+            assert_eq!(res.elts.len(), expected.elts.len());
+            assert!(expected.elts.iter().all(|elt| res.elts.contains(elt)));
+        }
+    }
+
+    #[test]
+    fn intervals_neg() {
+        assert_eq!(
+            -Interval { start: 2, stop: 5 },
+            Interval {
+                start: -5,
+                stop: -2
+            }
+        );
+        assert_eq!(-(-I2), I2);
+
+        let coll = IntervalCollection::from_tuples([(1, 2), (4, 5)]);
+        assert_eq!(format!("{}", &(-coll)), "[[-5, -4], [-2, -1]]");
+
+        let coll = IntervalCollection::from_tuples([(0.5, 1.5)]);
+        assert_eq!(format!("{}", &(-coll)), "[[-1.5, -0.5]]");
+    }
+
+    #[test]
+    fn intervals_since_until() {
+        let t = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+        let coll = IntervalCollection::from_tuples([
+            (t("2022-03-21T09:00:00Z"), t("2022-03-21T10:00:00Z")),
+            (t("2022-03-21T11:30:00Z"), t("2022-03-21T12:30:00Z")),
+            (t("2022-03-21T14:00:00Z"), t("2022-03-21T15:00:00Z")),
+        ]);
+        let noon = t("2022-03-21T12:00:00Z");
+
+        let since = coll.since(noon);
+        assert_eq!(since.elts.len(), 2);
+        assert_eq!(since.elts[0].start, noon);
+        assert_eq!(since.total_duration(), Duration::minutes(90));
+
+        let until = coll.until(noon);
+        assert_eq!(until.elts.len(), 2);
+        assert_eq!(until.elts[1].stop, noon);
+        assert_eq!(until.total_duration(), Duration::minutes(90));
+
+        assert!(coll.since(t("2022-03-21T15:00:00Z")).elts.is_empty());
+        assert_eq!(coll.until(t("2022-03-21T16:00:00Z")).elts.len(), 3);
+    }
+
+    #[test]
+    fn intervals_union_intervals() {
+        let mut ivs = vec![I5, I2, Interval { start: 0, stop: 2 }, I1, I4];
+        let res = union_intervals(&mut ivs);
+        assert_eq!(
+            res,
+            vec![
+                Interval { start: 0, stop: 2 },
+                Interval { start: 3, stop: 5 }
+            ]
+        );
+        assert_eq!(ivs[0], I1);
+
+        let mut ivs: Vec<Interval<i64>> = vec![];
+        assert!(union_intervals(&mut ivs).is_empty());
+
+        // compare with a quadratic merge on shuffled inputs
+        for seed in 0..20 {
+            let mut ivs = random_intervals(seed, 50, 1_000, 30);
+            let expected = naive_union(&ivs);
+            assert_eq!(union_intervals(&mut ivs), expected);
+        }
+    }
+
+    #[test]
+    fn intervals_add_unsorted() {
+        let left = IntervalCollection { elts: vec![I4, I1] };
+        let right = IntervalCollection { elts: vec![I5, I2] };
+        assert_eq!(format!("{}", &(&left + &right)), "[[0, 2], [3, 5]]");
+        let empty = IntervalCollection::builder();
+        assert_eq!(format!("{}", &(&left + &empty)), "[[0, 1], [3, 4]]");
+        assert_eq!(format!("{}", &(&empty + &empty)), "∅");
     }
 }
@@ -1,8 +1,12 @@
-use intervals::{Interval, IntervalCollection};
+use intervals::{union_many, Interval, IntervalCollection};
+use numpy::datetime::{units, Datetime};
 use numpy::{PyArray1, PyReadonlyArray1};
+use pyo3::exceptions::PyRuntimeError;
 use pyo3::types::IntoPyDict;
 use pyo3::{prelude::*, types::PyDict};
 
+/// Builds a collection sorted by start then stop, without duplicates, so that
+/// the arrays passed from Python need not be sorted
 fn get_ic(start: PyReadonlyArray1<i64>, stop: PyReadonlyArray1<i64>) -> IntervalCollection<i64> {
     let size1 = start.len();
     let size2 = stop.len();
@@ -15,9 +19,42 @@ fn get_ic(start: PyReadonlyArray1<i64>, stop: PyReadonlyArray1<i64>) -> Interval
             stop: *stop.get(i).unwrap(),
         })
     }
+    elts.sort();
+    elts.dedup();
     IntervalCollection { elts }
 }
 
+type Datetime64 = Datetime<units::Nanoseconds>;
+
+/// Same as get_ic, reinterpreting datetime64[ns] values as i64 nanoseconds
+fn get_ic_dt(
+    start: PyReadonlyArray1<Datetime64>,
+    stop: PyReadonlyArray1<Datetime64>,
+) -> IntervalCollection<i64> {
+    let size = std::cmp::min(start.len(), stop.len());
+
+    let mut elts = Vec::<Interval<i64>>::with_capacity(size);
+    for i in 0..size {
+        elts.push(Interval {
+            start: (*start.get(i).unwrap()).into(),
+            stop: (*stop.get(i).unwrap()).into(),
+        })
+    }
+    elts.sort();
+    elts.dedup();
+    IntervalCollection { elts }
+}
+
+fn wrap_ic_dt<'a>(py: Python<'a>, res: IntervalCollection<i64>) -> PyResult<&'a PyDict> {
+    let start: Vec<Datetime64> = res.elts.iter().map(|elt| elt.start.into()).collect();
+    let stop: Vec<Datetime64> = res.elts.iter().map(|elt| elt.stop.into()).collect();
+
+    let wrapped_res = PyDict::new(py);
+    wrapped_res.set_item("start", PyArray1::from_vec(py, start))?;
+    wrapped_res.set_item("stop", PyArray1::from_vec(py, stop))?;
+    Ok(wrapped_res)
+}
+
 #[pyfunction]
 fn interval_and(py: Python, start1: i64, stop1: i64, start2: i64, stop2: i64) -> PyResult<&PyDict> {
     let left = Interval {
@@ -28,7 +65,7 @@ fn interval_and(py: Python, start1: i64, stop1: i64, start2: i64, stop2: i64) ->
         start: start2,
         stop: stop2,
     };
-    let res = match &left & &right {
+    let res = match left & right {
         None => [("empty", true)].into_py_dict(py),
         Some(Interval { start, stop }) => [("start", start), ("stop", stop)].into_py_dict(py),
     };
@@ -110,7 +147,7 @@ fn collection_add<'a>(
 ) -> PyResult<&'a PyDict> {
     let left = get_ic(start1, stop1);
     let right = get_ic(start2, stop2);
-    let res = left + right;
+    let res = union_many(&[&left, &right]);
 
     let start: Vec<i64> = res.elts.iter().map(|elt| elt.start).collect();
     let stop: Vec<i64> = res.elts.iter().map(|elt| elt.stop).collect();
@@ -134,7 +171,8 @@ fn collection_addi<'a>(
         start: start2,
         stop: stop2,
     };
-    let res = &left + &right;
+    let mut res = left.normalize();
+    res.insert(right);
 
     let start: Vec<i64> = res.elts.iter().map(|elt| elt.start).collect();
     let stop: Vec<i64> = res.elts.iter().map(|elt| elt.stop).collect();
@@ -166,6 +204,33 @@ fn interval_sub(py: Python, start1: i64, stop1: i64, start2: i64, stop2: i64) ->
     Ok(wrapped_res)
 }
 
+#[pyfunction]
+fn interval_xor<'a>(
+    py: Python<'a>,
+    start1: i64,
+    stop1: i64,
+    start2: i64,
+    stop2: i64,
+) -> PyResult<&'a PyDict> {
+    let left = Interval {
+        start: start1,
+        stop: stop1,
+    };
+    let right = Interval {
+        start: start2,
+        stop: stop2,
+    };
+
+    let res = left ^ right;
+    let start: Vec<i64> = res.elts.iter().map(|elt| elt.start).collect();
+    let stop: Vec<i64> = res.elts.iter().map(|elt| elt.stop).collect();
+
+    let wrapped_res = PyDict::new(py);
+    wrapped_res.set_item("start", PyArray1::from_vec(py, start))?;
+    wrapped_res.set_item("stop", PyArray1::from_vec(py, stop))?;
+    Ok(wrapped_res)
+}
+
 #[pyfunction]
 fn collection_sub<'a>(
     py: Python<'a>,
@@ -211,6 +276,173 @@ fn collection_subi<'a>(
     Ok(wrapped_res)
 }
 
+#[pyfunction]
+fn collection_total_duration(start: PyReadonlyArray1<i64>, stop: PyReadonlyArray1<i64>) -> i64 {
+    get_ic(start, stop).covered_duration()
+}
+
+#[pyfunction]
+fn collection_and_dt<'a>(
+    py: Python<'a>,
+    start1: PyReadonlyArray1<Datetime64>,
+    stop1: PyReadonlyArray1<Datetime64>,
+    start2: PyReadonlyArray1<Datetime64>,
+    stop2: PyReadonlyArray1<Datetime64>,
+) -> PyResult<&'a PyDict> {
+    let left = get_ic_dt(start1, stop1);
+    let right = get_ic_dt(start2, stop2);
+    wrap_ic_dt(py, &left & &right)
+}
+
+#[pyfunction]
+fn collection_add_dt<'a>(
+    py: Python<'a>,
+    start1: PyReadonlyArray1<Datetime64>,
+    stop1: PyReadonlyArray1<Datetime64>,
+    start2: PyReadonlyArray1<Datetime64>,
+    stop2: PyReadonlyArray1<Datetime64>,
+) -> PyResult<&'a PyDict> {
+    let left = get_ic_dt(start1, stop1);
+    let right = get_ic_dt(start2, stop2);
+    wrap_ic_dt(py, union_many(&[&left, &right]))
+}
+
+#[pyfunction]
+fn collection_sub_dt<'a>(
+    py: Python<'a>,
+    start1: PyReadonlyArray1<Datetime64>,
+    stop1: PyReadonlyArray1<Datetime64>,
+    start2: PyReadonlyArray1<Datetime64>,
+    stop2: PyReadonlyArray1<Datetime64>,
+) -> PyResult<&'a PyDict> {
+    let left = get_ic_dt(start1, stop1);
+    let right = get_ic_dt(start2, stop2);
+    wrap_ic_dt(py, left - right)
+}
+
+#[pyfunction]
+fn process_numpy_datetime_array<'a>(
+    py: Python<'a>,
+    timestamps: PyReadonlyArray1<i64>,
+) -> PyResult<&'a PyDict> {
+    let timestamps = timestamps.as_array();
+    let monotonic = timestamps
+        .iter()
+        .zip(timestamps.iter().skip(1))
+        .all(|(previous, next)| previous <= next);
+
+    let wrapped_res = PyDict::new(py);
+    wrapped_res.set_item("count", timestamps.len())?;
+    wrapped_res.set_item("min", timestamps.iter().min().copied())?;
+    wrapped_res.set_item("max", timestamps.iter().max().copied())?;
+    wrapped_res.set_item("monotonic", monotonic)?;
+    Ok(wrapped_res)
+}
+
+// pyo3 0.20 expands #[pymethods] into impl blocks nested in a const item,
+// which recent rustc versions report as non_local_definitions: the lint is
+// only allowed in this module, until a pyo3 upgrade
+#[allow(unknown_lints, non_local_definitions)]
+mod classes {
+    use super::*;
+
+    #[pyclass(name = "Interval")]
+    #[derive(Clone, Copy)]
+    pub(crate) struct PyInterval {
+        inner: Interval<i64>,
+    }
+
+    #[pymethods]
+    impl PyInterval {
+        #[new]
+        fn new(start: i64, stop: i64) -> PyResult<Self> {
+            if start > stop {
+                return Err(PyRuntimeError::new_err(
+                    "Start value should be anterior to stop value",
+                ));
+            }
+            Ok(PyInterval {
+                inner: Interval { start, stop },
+            })
+        }
+
+        #[getter]
+        fn start(&self) -> i64 {
+            self.inner.start
+        }
+
+        #[getter]
+        fn stop(&self) -> i64 {
+            self.inner.stop
+        }
+
+        fn duration(&self) -> i64 {
+            self.inner.duration()
+        }
+
+        /// Membership in the half-open interval [start, stop), consistently with
+        /// the overlap semantics of the other operators
+        fn __contains__(&self, value: i64) -> bool {
+            self.inner.start <= value && value < self.inner.stop
+        }
+
+        fn __and__(&self, other: PyInterval) -> Option<PyInterval> {
+            (self.inner & other.inner).map(|inner| PyInterval { inner })
+        }
+
+        fn __add__(&self, other: PyInterval) -> PyIntervalCollection {
+            PyIntervalCollection {
+                inner: self.inner + other.inner,
+            }
+        }
+
+        fn __sub__(&self, other: PyInterval) -> PyIntervalCollection {
+            PyIntervalCollection {
+                inner: self.inner - other.inner,
+            }
+        }
+
+        fn __repr__(&self) -> String {
+            format!("{}", &self.inner)
+        }
+    }
+
+    #[pyclass(name = "IntervalCollection")]
+    pub(crate) struct PyIntervalCollection {
+        inner: IntervalCollection<i64>,
+    }
+
+    #[pymethods]
+    impl PyIntervalCollection {
+        #[new]
+        fn new(elts: Vec<PyInterval>) -> Self {
+            PyIntervalCollection {
+                inner: IntervalCollection {
+                    elts: elts.iter().map(|elt| elt.inner).collect(),
+                },
+            }
+        }
+
+        #[getter]
+        fn elts(&self) -> Vec<PyInterval> {
+            self.inner
+                .elts
+                .iter()
+                .map(|&inner| PyInterval { inner })
+                .collect()
+        }
+
+        fn __len__(&self) -> usize {
+            self.inner.elts.len()
+        }
+
+        fn __repr__(&self) -> String {
+            format!("{}", &self.inner)
+        }
+    }
+}
+use classes::{PyInterval, PyIntervalCollection};
+
 #[pymodule]
 #[pyo3(name = "core")]
 fn trafficrs(_py: Python, m: &PyModule) -> PyResult<()> {
@@ -226,7 +458,21 @@ fn trafficrs(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(collection_sub, m)?)?;
     m.add_function(wrap_pyfunction!(collection_subi, m)?)?;
 
+    m.add_function(wrap_pyfunction!(interval_xor, m)?)?;
+
+    m.add_function(wrap_pyfunction!(collection_total_duration, m)?)?;
+
+    m.add_function(wrap_pyfunction!(collection_and_dt, m)?)?;
+    m.add_function(wrap_pyfunction!(collection_add_dt, m)?)?;
+    m.add_function(wrap_pyfunction!(collection_sub_dt, m)?)?;
+
+    m.add_function(wrap_pyfunction!(process_numpy_datetime_array, m)?)?;
+
+    m.add_class::<PyInterval>()?;
+    m.add_class::<PyIntervalCollection>()?;
+
     Ok(())
 }
 
+pub mod geo;
 pub mod intervals;